@@ -0,0 +1,26 @@
+//! Makes `memory.x` visible to `cortex-m-rt`'s linker invocation, and adds
+//! `defmt`'s linker script only for builds that actually pull it in.
+
+use std::env;
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+
+fn main() {
+    let out = PathBuf::from(env::var_os("OUT_DIR").unwrap());
+    File::create(out.join("memory.x"))
+        .unwrap()
+        .write_all(include_bytes!("memory.x"))
+        .unwrap();
+
+    println!("cargo:rustc-link-search={}", out.display());
+    println!("cargo:rerun-if-changed=memory.x");
+
+    // `.cargo/config.toml`'s rustflags apply to every build regardless of
+    // feature selection, so `-Tdefmt.x` can't live there -- it would break
+    // linking for the default, defmt-free build, which never emits the
+    // sections that script expects. Only the `diagnostics` feature needs it.
+    if env::var_os("CARGO_FEATURE_DIAGNOSTICS").is_some() {
+        println!("cargo:rustc-link-arg=-Tdefmt.x");
+    }
+}
@@ -0,0 +1,116 @@
+//! HID transport: an NKRO keyboard interface plus a consumer-control
+//! interface, replacing keyberon's boot-protocol `Class`.
+//!
+//! `keyberon::key_code::KbHidReport` is a 6-key boot report: past 6
+//! simultaneously held keys, further presses are silently dropped, and it has
+//! no consumer-page usages, so `MediaBack`/`MediaForward` (see `layout.rs`)
+//! were smuggled in as keyboard-page "media" keycodes. `usbd-human-interface-device`
+//! gives us an NKRO bitmap report (every held key gets its own bit, so
+//! rollover is effectively unlimited) plus a dedicated consumer-control
+//! interface for real media/volume usages.
+//!
+//! `keyberon::layout::Layout` still owns the keymap; each scan we translate
+//! the `KeyCode`s it emits into these two reports instead of a `KbHidReport`.
+//! The LED output report (Caps/Num/Scroll lock) the host sends back is a
+//! `PackedStruct`-derived bitfield, as the HID crate expects.
+
+use frunk::{HCons, HNil};
+use keyberon::key_code::KeyCode;
+use rp2040_hal::usb::UsbBus;
+use usb_device::class_prelude::UsbBusAllocator;
+use usbd_human_interface_device::device::consumer::{
+    ConsumerControl, ConsumerControlConfig, MultipleConsumerReport,
+};
+use usbd_human_interface_device::device::keyboard::{NKROBootKeyboard, NKROBootKeyboardConfig};
+use usbd_human_interface_device::page::{Consumer, Keyboard as KeyboardPage};
+use usbd_human_interface_device::prelude::*;
+use usbd_human_interface_device::UsbHidError;
+
+/// The composite HID class: one USB interface collection exposing both the
+/// NKRO keyboard and the consumer-control device. The `HCons` nesting order
+/// is `add_device`'s: each call prepends, so the last device added
+/// (`ConsumerControl`) ends up as the outer/head type.
+pub type Hid = UsbHidClass<
+    'static,
+    UsbBus,
+    HCons<ConsumerControl<'static, UsbBus>, HCons<NKROBootKeyboard<'static, UsbBus>, HNil>>,
+>;
+
+pub fn new_hid(usb_bus: &'static UsbBusAllocator<UsbBus>) -> Hid {
+    UsbHidClassBuilder::new()
+        .add_device(NKROBootKeyboardConfig::default())
+        .add_device(ConsumerControlConfig::default())
+        .build(usb_bus)
+}
+
+/// Reads the host's latest Caps/Num/Scroll-lock output report, if one has
+/// come in since the last read.
+pub fn read_leds(hid: &mut Hid) -> Option<crate::leds::Leds> {
+    // `read_report` surfaces `usb_device::UsbError`, not `UsbHidError`: there's
+    // nothing actionable to do with any of its variants here (WouldBlock just
+    // means the host hasn't sent a new output report), so collapse them all.
+    hid.device::<NKROBootKeyboard<'static, UsbBus>, _>()
+        .read_report()
+        .ok()
+        .map(|report| {
+            crate::leds::Leds::from_report(report.caps_lock, report.num_lock, report.scroll_lock)
+        })
+}
+
+/// Maps a `MediaBack`/`MediaForward`-style keyberon `KeyCode` onto its real
+/// consumer-page usage, so it's reported on the consumer-control interface
+/// instead of being smuggled onto the keyboard page.
+fn consumer_usage(kc: KeyCode) -> Option<Consumer> {
+    match kc {
+        KeyCode::MediaBack => Some(Consumer::ScanPreviousTrack),
+        KeyCode::MediaForward => Some(Consumer::ScanNextTrack),
+        KeyCode::MediaVolUp => Some(Consumer::VolumeIncrement),
+        KeyCode::MediaVolDown => Some(Consumer::VolumeDecrement),
+        KeyCode::MediaPlayPause => Some(Consumer::PlayPause),
+        _ => None,
+    }
+}
+
+/// Splits a scan's keyberon keycodes between the keyboard-page NKRO bitmap
+/// and the consumer-control usages, and writes both reports. Called once per
+/// scan, after `layout.tick()`.
+pub fn write_reports(
+    hid: &mut Hid,
+    keycodes: impl Iterator<Item = KeyCode>,
+) -> Result<(), UsbHidError> {
+    let mut consumer_codes = [Consumer::Unassigned; 4];
+    let mut n_consumer = 0;
+
+    let keyboard_usages = keycodes.filter_map(|kc| match consumer_usage(kc) {
+        Some(usage) => {
+            if n_consumer < consumer_codes.len() {
+                consumer_codes[n_consumer] = usage;
+                n_consumer += 1;
+            }
+            None
+        }
+        None => Some(KeyboardPage::from(kc as u8)),
+    });
+
+    // `Duplicate` fires whenever the keyboard bitmap is unchanged from the
+    // last report -- which is the common case for a standalone consumer key,
+    // since it's filtered out of `keyboard_usages` above and leaves nothing
+    // else behind. Don't let that (or `WouldBlock`) short-circuit the
+    // consumer-control write below via `?`.
+    let keyboard_result = hid
+        .device::<NKROBootKeyboard<'static, UsbBus>, _>()
+        .write_report(keyboard_usages);
+    match keyboard_result {
+        Ok(()) | Err(UsbHidError::Duplicate) | Err(UsbHidError::WouldBlock) => {}
+        Err(e) => return Err(e),
+    }
+
+    // `ConsumerControl::write_report` surfaces `usb_device::UsbError`, not
+    // `UsbHidError`; a dropped consumer report just means a stale volume/media
+    // keypress doesn't reach the host this tick, so it isn't worth bubbling up.
+    let _ = hid
+        .device::<ConsumerControl<'static, UsbBus>, _>()
+        .write_report(&MultipleConsumerReport { codes: consumer_codes });
+
+    Ok(())
+}
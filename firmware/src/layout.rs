@@ -3,7 +3,7 @@ const LAYER_COUNT: usize = 3; // adjust to the number of layers defined below
 pub const COLS: usize = 12;
 pub const ROWS: usize = 4;
 
-use keyberon::action::{Action, m};
+use keyberon::action::{m, Action, HoldTapAction, HoldTapConfig};
 use keyberon::key_code::KeyCode;
 use keyberon::layout;
 
@@ -26,14 +26,101 @@ const STB: Action<()> = m(&[KeyCode::RShift, KeyCode::Tab].as_slice());
 const BCK: Action<()> = Action::KeyCode(KeyCode::MediaBack);
 const FWD: Action<()> = Action::KeyCode(KeyCode::MediaForward);
 
+// Hold-tap tuning -- see `keyberon::action::{HoldTapAction, HoldTapConfig}`.
+// `layout.tick()` is already called every `SCAN_TIME_US` from
+// `process_kbd_events`, so `HOLD_TAP_TIMEOUT` below is in units of that scan
+// period, not milliseconds.
+const HOLD_TAP_TIMEOUT: u16 = 200;
+
+// `HoldTapConfig::Default` only resolves to a hold once the timeout elapses.
+// `HoldTapConfig::HoldOnOtherKeyPress` resolves to a hold as soon as any
+// other key goes down, which favours real modifier holds over fast rolls.
+// `HoldTapConfig::PermissiveHold` resolves to a hold once another key is
+// *released* inside the window -- a roll stays a tap, while a quick
+// press-and-release under the held key still acts as a modifier.
+// Pick whichever matches your typing style; this board ships with the last.
+const HOLD_TAP_CONFIG: HoldTapConfig = HoldTapConfig::PermissiveHold;
+
+// Home-row mods, left hand: A S D F -> Ctrl Alt Gui Shift when held.
+const HOME_A: Action<()> = Action::HoldTap(&HoldTapAction {
+    timeout: HOLD_TAP_TIMEOUT,
+    tap_hold_interval: 0,
+    config: HOLD_TAP_CONFIG,
+    hold: Action::KeyCode(KeyCode::LCtrl),
+    tap: Action::KeyCode(KeyCode::A),
+});
+const HOME_S: Action<()> = Action::HoldTap(&HoldTapAction {
+    timeout: HOLD_TAP_TIMEOUT,
+    tap_hold_interval: 0,
+    config: HOLD_TAP_CONFIG,
+    hold: Action::KeyCode(KeyCode::LAlt),
+    tap: Action::KeyCode(KeyCode::S),
+});
+const HOME_D: Action<()> = Action::HoldTap(&HoldTapAction {
+    timeout: HOLD_TAP_TIMEOUT,
+    tap_hold_interval: 0,
+    config: HOLD_TAP_CONFIG,
+    hold: Action::KeyCode(KeyCode::LGui),
+    tap: Action::KeyCode(KeyCode::D),
+});
+const HOME_F: Action<()> = Action::HoldTap(&HoldTapAction {
+    timeout: HOLD_TAP_TIMEOUT,
+    tap_hold_interval: 0,
+    config: HOLD_TAP_CONFIG,
+    hold: Action::KeyCode(KeyCode::LShift),
+    tap: Action::KeyCode(KeyCode::F),
+});
+
+// Home-row mods, right hand: J K L ; -> Shift Gui Alt Ctrl when held,
+// mirroring the left hand.
+const HOME_J: Action<()> = Action::HoldTap(&HoldTapAction {
+    timeout: HOLD_TAP_TIMEOUT,
+    tap_hold_interval: 0,
+    config: HOLD_TAP_CONFIG,
+    hold: Action::KeyCode(KeyCode::RShift),
+    tap: Action::KeyCode(KeyCode::J),
+});
+const HOME_K: Action<()> = Action::HoldTap(&HoldTapAction {
+    timeout: HOLD_TAP_TIMEOUT,
+    tap_hold_interval: 0,
+    config: HOLD_TAP_CONFIG,
+    hold: Action::KeyCode(KeyCode::RGui),
+    tap: Action::KeyCode(KeyCode::K),
+});
+const HOME_L: Action<()> = Action::HoldTap(&HoldTapAction {
+    timeout: HOLD_TAP_TIMEOUT,
+    tap_hold_interval: 0,
+    config: HOLD_TAP_CONFIG,
+    hold: Action::KeyCode(KeyCode::RAlt),
+    tap: Action::KeyCode(KeyCode::L),
+});
+const HOME_SCOLON: Action<()> = Action::HoldTap(&HoldTapAction {
+    timeout: HOLD_TAP_TIMEOUT,
+    tap_hold_interval: 0,
+    config: HOLD_TAP_CONFIG,
+    hold: Action::KeyCode(KeyCode::RCtrl),
+    tap: Action::KeyCode(KeyCode::SColon),
+});
+
+// Thumb layer-shift: tap for Space, hold to reach the Function layer --
+// alongside the dedicated `(1)`/`(2)` momentary-layer keys already on the
+// thumb cluster.
+const SPC_LAYER: Action<()> = Action::HoldTap(&HoldTapAction {
+    timeout: HOLD_TAP_TIMEOUT,
+    tap_hold_interval: 0,
+    config: HOLD_TAP_CONFIG,
+    hold: Action::Layer(2),
+    tap: Action::KeyCode(KeyCode::Space),
+});
+
 #[rustfmt::skip]
 pub static LAYERS: layout::Layers<COLS, ROWS, LAYER_COUNT, ()> = layout::layout! {
 
     { // base layer -- don't worry about lhe key names, this will reflect your OS keyboard layout
-        [ Escape Q    W    E    R    T         Y    U    I    O    P    BSpace ],
-        [ Tab    A    S    D    F    G         H    J    K    L    ;     Enter ],
-        [ LShift Z    X    C    V    B         N    M    ,    .    /    RShift ],
-        [ n n n         LCtrl Space (1)        LGui Space RAlt             n n n ],
+        [ Escape Q          W          E          R          T              Y    U          I          O         P    BSpace ],
+        [ Tab   {HOME_A}   {HOME_S}   {HOME_D}   {HOME_F}    G              H   {HOME_J}    {HOME_K}   {HOME_L}  {HOME_SCOLON} Enter ],
+        [ LShift Z          X          C          V          B              N    M          ,          .         /    RShift ],
+        [ n n n                        LCtrl     {SPC_LAYER} (1)            LGui {SPC_LAYER} RAlt                      n n n ],
     }
     { // NumNav
         [ t        Tab  Home  Up   End  PgUp      n    7    8    9    n    Delete ],
@@ -0,0 +1,151 @@
+//! WS2812/SK6812 underglow, bit-banged over SPI.
+//!
+//! WS2812 timing doesn't map onto a UART word, but it does map onto SPI: each
+//! logical LED bit is split into 3 SPI bits clocked at ~3 MHz, so a logical 0
+//! becomes `0b100` and a logical 1 becomes `0b110`, MSB first, GRB byte order.
+//! `ws2812-spi` does that expansion for us; we only need an SPI peripheral
+//! clocked in the right ballpark and more than 50 us of idle between frames
+//! (also handled by the crate, via a burst of zero bytes).
+//!
+//! `Leds` below just latches the host's Caps/Num/Scroll-lock output report. It was
+//! originally read straight off `keyberon::Class`'s second generic
+//! parameter; the HID stack migration to `usbd-human-interface-device` (see
+//! `hid.rs`) replaced that with reading the NKRO keyboard interface's output
+//! report instead, so `Leds` is populated via `hid::read_leds` rather than
+//! the `keyberon::keyboard::Leds` trait. The actual animation lives in
+//! `Underglow`, ticked once per scan from `process_kbd_events`, after
+//! `layout.tick()`, so it can react to both the current layer and those
+//! latched lock flags.
+
+use embedded_hal::spi::SpiBus;
+use smart_leds::{SmartLedsWrite, RGB8};
+use ws2812_spi::Ws2812;
+
+/// Number of addressable LEDs in the underglow chain.
+pub const NUM_LEDS: usize = 12;
+
+/// SPI clock used to fake WS2812 timing (3 SPI bits per LED bit).
+pub const LED_SPI_BAUDRATE_HZ: u32 = 3_000_000;
+
+/// HID LED output flags reported by the host, as read back from the NKRO
+/// keyboard interface's `KeyboardLedsReport` (see `hid::read_leds`). All
+/// three drive `Underglow::tick`'s color override, Caps Lock taking priority
+/// over Num Lock over Scroll Lock when more than one is latched at once.
+#[derive(Default, Clone, Copy)]
+pub struct Leds {
+    caps_lock: bool,
+    num_lock: bool,
+    scroll_lock: bool,
+}
+
+impl Leds {
+    pub fn from_report(caps_lock: bool, num_lock: bool, scroll_lock: bool) -> Self {
+        Leds {
+            caps_lock,
+            num_lock,
+            scroll_lock,
+        }
+    }
+
+    fn caps_lock(&self) -> bool {
+        self.caps_lock
+    }
+
+    fn num_lock(&self) -> bool {
+        self.num_lock
+    }
+
+    fn scroll_lock(&self) -> bool {
+        self.scroll_lock
+    }
+}
+
+/// Solid per-layer color, indexed by `QuackenLayout`'s layer id.
+const LAYER_COLORS: [RGB8; 3] = [
+    RGB8 { r: 0, g: 0, b: 20 },  // base
+    RGB8 { r: 20, g: 8, b: 0 },  // NumNav
+    RGB8 { r: 0, g: 20, b: 8 },  // Function
+];
+
+/// Overrides the layer color while Caps Lock is latched on.
+const CAPS_LOCK_COLOR: RGB8 = RGB8 { r: 20, g: 0, b: 0 };
+
+/// Overrides the layer color while Num Lock is latched on (and Caps isn't).
+const NUM_LOCK_COLOR: RGB8 = RGB8 { r: 20, g: 20, b: 0 };
+
+/// Overrides the layer color while Scroll Lock is latched on (and neither
+/// Caps nor Num is).
+const SCROLL_LOCK_COLOR: RGB8 = RGB8 { r: 20, g: 0, b: 20 };
+
+/// Breathing period, in `tick()` calls (one per `SCAN_TIME_US`).
+const BREATH_PERIOD_TICKS: u32 = 2_000;
+
+/// Floor under the breathing brightness ramp, so the strip dims rather than
+/// fully blanks at the trough.
+const BREATH_MIN: u8 = 24;
+
+/// Minimum gap between full-strip SPI refreshes, in `tick()` calls. A refresh
+/// blocks for `NUM_LEDS * 24 logical bits * 3 SPI bits / LED_SPI_BAUDRATE_HZ`
+/// -- about 290 us for 12 LEDs at 3 MHz -- which would eat roughly a third of
+/// a 1 ms `SCAN_TIME_US` scan if repainted every tick. 16 ticks is ~60 Hz,
+/// well above what the breathing animation needs to look smooth.
+const REFRESH_PERIOD_TICKS: u32 = 16;
+
+/// Underglow animation state, ticked once per scan.
+pub struct Underglow<SPI> {
+    strip: Ws2812<SPI>,
+    frame: u32,
+}
+
+impl<SPI, E> Underglow<SPI>
+where
+    SPI: SpiBus<u8, Error = E>,
+{
+    pub fn new(spi: SPI) -> Self {
+        Underglow {
+            strip: Ws2812::new(spi),
+            frame: 0,
+        }
+    }
+
+    /// Recomputes the underglow color for `layer` and, once every
+    /// `REFRESH_PERIOD_TICKS`, pushes a frame to the strip. Call once per
+    /// scan, after `layout.tick()`, so switching to the NumNav or Function
+    /// layer via `(1)`/`(2)` recolors the strip.
+    pub fn tick(&mut self, layer: usize, leds: &Leds) {
+        self.frame = self.frame.wrapping_add(1);
+        if self.frame % REFRESH_PERIOD_TICKS != 0 {
+            return;
+        }
+
+        let base = if leds.caps_lock() {
+            CAPS_LOCK_COLOR
+        } else if leds.num_lock() {
+            NUM_LOCK_COLOR
+        } else if leds.scroll_lock() {
+            SCROLL_LOCK_COLOR
+        } else {
+            LAYER_COLORS[layer.min(LAYER_COLORS.len() - 1)]
+        };
+
+        let color = scale(base, breath(self.frame));
+        let _ = self.strip.write(core::iter::repeat(color).take(NUM_LEDS));
+    }
+}
+
+/// Triangle-wave breathing brightness, `BREATH_PERIOD_TICKS` long, ramping
+/// between `BREATH_MIN` and 255 so the strip dims rather than blanks.
+fn breath(frame: u32) -> u8 {
+    let phase = frame % BREATH_PERIOD_TICKS;
+    let half = BREATH_PERIOD_TICKS / 2;
+    let level = if phase < half { phase } else { BREATH_PERIOD_TICKS - phase };
+    BREATH_MIN + (level * (255 - BREATH_MIN as u32) / half) as u8
+}
+
+fn scale(c: RGB8, brightness: u8) -> RGB8 {
+    RGB8 {
+        r: (c.r as u16 * brightness as u16 / 255) as u8,
+        g: (c.g as u16 * brightness as u16 / 255) as u8,
+        b: (c.b as u16 * brightness as u16 / 255) as u8,
+    }
+}
@@ -1,10 +1,20 @@
 #![no_std]
 #![no_main]
 
+mod hid; // NKRO keyboard + consumer-control HID transport
 mod layout; // 3*6 key layout
+mod leds; // WS2812 underglow + HID LED state
 mod zero; // QuackenZero-specific matrix scanning
 
-// set the panic handler
+// Logging + panic handler: defmt-over-RTT when the `diagnostics` feature is
+// enabled, so matrix/USB state can be inspected live over SWD with
+// `probe-rs` without a logic analyzer; plain `panic_halt` otherwise, to keep
+// the default build small.
+#[cfg(feature = "diagnostics")]
+use defmt_rtt as _;
+#[cfg(feature = "diagnostics")]
+use panic_probe as _;
+#[cfg(not(feature = "diagnostics"))]
 use panic_halt as _;
 
 #[unsafe(link_section = ".boot2")]
@@ -17,37 +27,48 @@ pub static BOOT2: [u8; 256] = rp2040_boot2::BOOT_LOADER_W25Q080;
     dispatchers = [PIO0_IRQ_0, PIO0_IRQ_1, PIO1_IRQ_0]
 )]
 mod app {
+    use crate::hid::{self, Hid};
     use crate::layout::{self as kb_layout, QuackenLayout};
+    use crate::leds::{self, Underglow};
     use crate::zero::QuackenZeroMatrix;
 
     use core::convert::Infallible;
 
     use cortex_m::delay::Delay;
 
+    use fugit::RateExtU32;
+
     use rp2040_hal::{
         self, Clock,
         clocks::init_clocks_and_plls,
         fugit::MicrosDurationU32,
         gpio::Pins,
-        pac::CorePeripherals,
+        pac::{CorePeripherals, SPI0},
         sio::Sio,
+        spi::{Enabled, Spi},
         timer::{Alarm, Alarm0, Timer},
         usb::UsbBus,
         watchdog::Watchdog,
     };
 
-    use keyberon::{debounce::Debouncer, key_code::KbHidReport, layout::Layout};
+    use keyberon::{debounce::Debouncer, layout::Layout};
 
     use usb_device::{
-        // HACK: import the UsbClass trait, but still allow to use its name for a type later
-        class::UsbClass as _,
         class_prelude::UsbBusAllocator,
+        device::{StringDescriptors, UsbDeviceBuilder, UsbVidPid},
         prelude::UsbDeviceState,
     };
 
-    type UsbClass = keyberon::Class<'static, UsbBus, ()>;
+    type UsbClass = Hid;
     type UsbDevice = usb_device::device::UsbDevice<'static, UsbBus>;
 
+    // keyberon's shared V-USB test VID/PID -- there's no USB-IF allocation
+    // for this project, same as the boot-protocol class it replaces.
+    const USB_VID: u16 = 0x16c0;
+    const USB_PID: u16 = 0x27db;
+
+    type LedSpi = Spi<Enabled, SPI0, crate::zero::LedSpiPins, 8>;
+
     trait ResultExt<T> {
         fn get(self) -> T;
     }
@@ -65,6 +86,7 @@ mod app {
     const SCAN_TIME_US: u32 = 1_000;
     const WATCHDOG_TIME_US: u32 = 10_000;
     const EXTERNAL_XTAL_FREQ_HZ: u32 = 12_000_000;
+    const DEBOUNCE_CYCLES: u16 = 5;
 
     #[shared]
     struct Shared {
@@ -73,6 +95,10 @@ mod app {
         alarm: Alarm0,
         #[lock_free]
         watchdog: Watchdog,
+        // Only ever touched from `process_kbd_events`; shared (rather than
+        // local) so the animation state is free to grow a second reader later.
+        #[lock_free]
+        underglow: Underglow<LedSpi>,
     }
 
     #[local]
@@ -82,6 +108,9 @@ mod app {
         debouncer: Debouncer<[[bool; kb_layout::COLS]; kb_layout::ROWS]>,
         delay: Delay,
         timer: Timer,
+        // Latest Caps/Num/Scroll-lock state; the host only sends a new
+        // output report on change, so this is carried over between scans.
+        leds: leds::Leds,
     }
 
     #[init(local = [bus: Option<UsbBusAllocator<UsbBus>> = None])]
@@ -131,12 +160,33 @@ mod app {
         *c.local.bus = Some(UsbBusAllocator::new(usb));
         let usb_bus = c.local.bus.as_ref().unwrap();
 
-        let usb_class = keyberon::new_class(usb_bus, ());
-        let usb_dev = keyberon::new_device(usb_bus);
+        let usb_class = hid::new_hid(usb_bus);
+        let usb_dev = UsbDeviceBuilder::new(usb_bus, UsbVidPid(USB_VID, USB_PID))
+            .strings(&[StringDescriptors::default()
+                .manufacturer("Quacken")
+                .product("Quacken Zero")
+                .serial_number(env!("CARGO_PKG_VERSION"))])
+            .expect("at most one string-descriptor language is provided")
+            .build();
 
         watchdog.start(MicrosDurationU32::micros(WATCHDOG_TIME_US));
 
-        let Ok(matrix) = QuackenZeroMatrix::new_sparkfun_rp2040(pins);
+        let Ok((matrix, led_spi_pins)) = QuackenZeroMatrix::new_sparkfun_rp2040(pins);
+
+        let led_spi = Spi::<_, _, _, 8>::new(c.device.SPI0, led_spi_pins).init(
+            &mut resets,
+            clocks.peripheral_clock.freq(),
+            leds::LED_SPI_BAUDRATE_HZ.Hz(),
+            embedded_hal::spi::MODE_0,
+        );
+        let underglow = Underglow::new(led_spi);
+
+        #[cfg(feature = "diagnostics")]
+        defmt::info!(
+            "Quacken diagnostics enabled: scan_time={=u32}us debounce={=u16} cycles",
+            SCAN_TIME_US,
+            DEBOUNCE_CYCLES,
+        );
 
         (
             Shared {
@@ -144,6 +194,7 @@ mod app {
                 usb_class,
                 alarm,
                 watchdog,
+                underglow,
             },
             Local {
                 matrix,
@@ -151,10 +202,11 @@ mod app {
                 debouncer: Debouncer::new(
                     [[false; kb_layout::COLS]; kb_layout::ROWS],
                     [[false; kb_layout::COLS]; kb_layout::ROWS],
-                    5,
+                    DEBOUNCE_CYCLES,
                 ),
                 delay,
                 timer,
+                leds: leds::Leds::default(),
             },
         )
     }
@@ -169,9 +221,7 @@ mod app {
         let usb = c.shared.usb_dev;
         let kb = c.shared.usb_class;
         (usb, kb).lock(|usb, kb| {
-            if usb.poll(&mut [kb]) {
-                kb.poll();
-            }
+            usb.poll(&mut [kb]);
         });
     }
 
@@ -179,8 +229,8 @@ mod app {
     #[task(
         binds = TIMER_IRQ_0,
         priority = 1,
-        local = [matrix, layout, debouncer, delay, timer],
-        shared = [alarm, watchdog, usb_dev, usb_class],
+        local = [matrix, layout, debouncer, delay, timer, leds],
+        shared = [alarm, watchdog, usb_dev, usb_class, underglow],
     )]
     fn process_kbd_events(mut c: process_kbd_events::Context) {
         c.shared.alarm.lock(|a| {
@@ -198,24 +248,37 @@ mod app {
             .debouncer
             .events(c.local.matrix.get_with_delay(delay_1us).get())
         {
+            #[cfg(feature = "diagnostics")]
+            defmt::debug!("layout event: {}", defmt::Debug2Format(&event));
+
             c.local.layout.event(event);
         }
 
         c.local.layout.tick();
 
-        if c.shared.usb_dev.lock(|d| d.state()) != UsbDeviceState::Configured {
+        let layer = c.local.layout.current_layer();
+        c.shared.usb_class.lock(|hid| {
+            if let Some(leds) = hid::read_leds(hid) {
+                *c.local.leds = leds;
+            }
+        });
+        c.shared.underglow.tick(layer, c.local.leds);
+
+        let usb_state = c.shared.usb_dev.lock(|d| d.state());
+        #[cfg(feature = "diagnostics")]
+        defmt::trace!("usb state: {}", defmt::Debug2Format(&usb_state));
+
+        if usb_state != UsbDeviceState::Configured {
             return;
         }
 
-        let report: KbHidReport = c.local.layout.keycodes().collect();
-        if !c
+        // Drives idle-rate/NKRO repeat timing; SCAN_TIME_US already matches
+        // the ~1ms cadence the HID crate expects this on.
+        c.shared.usb_class.lock(|hid| hid.tick()).ok();
+
+        let _ = c
             .shared
             .usb_class
-            .lock(|k| k.device_mut().set_keyboard_report(report.clone()))
-        {
-            return;
-        }
-
-        while let Ok(0) = c.shared.usb_class.lock(|k| k.write(report.as_bytes())) {}
+            .lock(|hid| hid::write_reports(hid, c.local.layout.keycodes()));
     }
 }
@@ -48,6 +48,13 @@ type KbOutputPin = gpio::Pin<gpio::DynPinId, gpio::FunctionSioOutput, gpio::Pull
 
 pub type QuackenZeroMatrix = Col2RowMatrix<KbOutputPin, KbInputPin>;
 
+// SPI0 pins (TX, SCK) left over by `new_sparkfun_rp2040`, handed to the
+// caller for the WS2812 underglow -- no MISO, since the strip is write-only.
+pub type LedSpiPins = (
+    gpio::Pin<gpio::bank0::Gpio19, gpio::FunctionSpi, gpio::PullDown>,
+    gpio::Pin<gpio::bank0::Gpio18, gpio::FunctionSpi, gpio::PullDown>,
+);
+
 use core::convert::Infallible;
 
 pub struct Col2RowMatrix<C, R>
@@ -115,8 +122,20 @@ where
     }
 
     /// Creates a new SparkFun ProMicro RP2040 matrix.
-    pub fn new_sparkfun_rp2040(pins: gpio::Pins) -> Result<QuackenZeroMatrix, Infallible> {
-        QuackenZeroMatrix::new(
+    ///
+    /// This pinout only uses 14 of the board's GPIOs; GPIO18/19 are free and
+    /// handed back as `LedSpiPins` (SPI0 SCK/TX) so the caller can drive the
+    /// WS2812 underglow without fighting the matrix for pins -- see
+    /// `main.rs`.
+    pub fn new_sparkfun_rp2040(
+        pins: gpio::Pins,
+    ) -> Result<(QuackenZeroMatrix, LedSpiPins), Infallible> {
+        let led_spi_pins = (
+            pins.gpio19.into_function::<gpio::FunctionSpi>(), // SPI0 TX (MOSI)
+            pins.gpio18.into_function::<gpio::FunctionSpi>(), // SPI0 SCK
+        );
+
+        let matrix = QuackenZeroMatrix::new(
             [
                 pins.gpio8.into_push_pull_output().into_dyn_pin(),
                 pins.gpio7.into_push_pull_output().into_dyn_pin(),
@@ -135,7 +154,9 @@ where
                 pins.gpio26.into_pull_down_input().into_dyn_pin(), // promicro 18
                 pins.gpio21.into_pull_down_input().into_dyn_pin(), // promicro 10
             ],
-        )
+        )?;
+
+        Ok((matrix, led_spi_pins))
     }
 
     // To use after creating the QuackenZeroMatrix if the microcontroller was soldered face down.
@@ -174,6 +195,10 @@ where
             }
             col.set_low()?;
         }
+
+        #[cfg(feature = "diagnostics")]
+        defmt::trace!("raw matrix (pre-debounce): {}", defmt::Debug2Format(&keys));
+
         Ok(keys)
     }
 